@@ -5,37 +5,260 @@
 //! ```no_run
 //! # #[async_std::main]
 //! # async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-//! let req = surf::get("https://httpbin.org/retry/2");
-//! let client = surf::client().with(surf::middleware::Retry::new(5));
+//! let req = surf::get("https://httpbin.org/status/503");
+//! // Retry up to 5 times, waiting at most 30s per attempt and 120s in total.
+//! let client = surf::client().with(surf::middleware::RetryAfter::new(5, 30, 120));
 //! let mut res = client.send(req).await?;
 //! dbg!(res.body_string().await?);
 //! # Ok(()) }
 //! ```
 
 use std::fmt::Arguments;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
+use crate::http::headers::HeaderName;
 use crate::http::{headers, StatusCode};
 use crate::middleware::{Middleware, Next, Request, Response};
-use crate::{Client, Result};
-// use chrono::*;
+use crate::{Client, Error, Result};
 use async_std::task;
-use chrono::NaiveDateTime;
-use time;
 
-// List of acceptible 300-series redirect codes.
+// Response status codes that are treated as retryable, independent of the
+// `is_transient_error` predicate (which only inspects the error path).
 const RETRY_AFTER_CODES: &[StatusCode] = &[
     StatusCode::MovedPermanently,
     StatusCode::TooManyRequests,
     StatusCode::ServiceUnavailable,
+    StatusCode::RequestTimeout,
+    StatusCode::GatewayTimeout,
 ];
 
-/// A middleware which retries throttled requests.
+/// The format a configured reset header's value is expected to be in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetFormat {
+    /// An integer number of seconds to wait, as used by the standard `Retry-After` header.
+    Seconds,
+    /// An absolute Unix timestamp (seconds since the epoch) at which the limit resets, as used
+    /// by headers like `X-RateLimit-Reset`. The delay is `header_value - now`, floored at zero.
+    UnixTimestamp,
+    /// An HTTP-date, as used by the standard `Retry-After` header.
+    HttpDate,
+    /// Either an integer number of seconds or an HTTP-date, exactly as the `Retry-After`
+    /// header itself is specified. This is what `Retry-After` is registered with by default, so
+    /// it's inspected once rather than once per candidate format.
+    RetryAfter,
+}
+
+/// A response header that `RetryAfter` inspects to compute a retry delay, paired with the
+/// format its value is expected to be in.
+#[derive(Debug, Clone)]
+pub struct ResetHeader {
+    name: HeaderName,
+    format: ResetFormat,
+}
+
+impl ResetHeader {
+    /// Register a header to inspect for a retry delay, in the given format.
+    pub fn new(name: HeaderName, format: ResetFormat) -> Self {
+        Self { name, format }
+    }
+}
+
+/// Parse a single configured reset header's value into a delay, according to its format.
+/// Returns `None` if the value doesn't parse in the expected format. Every format resolves to
+/// a `RetryAfterValue` first, so the delay-from-now computation lives in one place
+/// (`RetryAfterValue::into_delay`) rather than being duplicated per format.
+fn resolve_reset_header(header: &ResetHeader, value: &str) -> Option<Duration> {
+    let value = match header.format {
+        ResetFormat::Seconds => value
+            .parse::<u64>()
+            .ok()
+            .map(|delay_sec| RetryAfterValue::Delay(Duration::new(delay_sec, 0))),
+        ResetFormat::UnixTimestamp => value
+            .parse::<i64>()
+            .ok()
+            .map(|reset_at| RetryAfterValue::DateTime(system_time_from_unix_secs(reset_at))),
+        ResetFormat::HttpDate => parse_http_date(value).map(RetryAfterValue::DateTime),
+        ResetFormat::RetryAfter => parse_retry_after(value).ok(),
+    }?;
+    Some(value.into_delay())
+}
+
+/// A parsed `Retry-After` header value: either a relative delay or an absolute point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAfterValue {
+    /// A relative delay, as sent by `Retry-After: <seconds>`.
+    Delay(Duration),
+    /// An absolute point in time, as sent by `Retry-After: <http-date>`.
+    DateTime(SystemTime),
+}
+
+impl RetryAfterValue {
+    /// The delay to wait before retrying. A `DateTime` in the past floors to `Duration::ZERO`.
+    pub fn into_delay(self) -> Duration {
+        match self {
+            RetryAfterValue::Delay(delay) => delay,
+            RetryAfterValue::DateTime(at) => {
+                at.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO)
+            }
+        }
+    }
+}
+
+/// Parse a `Retry-After` header value, independent of the middleware: either an integer number
+/// of seconds, or one of the three RFC 7231 HTTP-date formats (IMF-fixdate, RFC 850, asctime).
+///
+/// reference: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Retry-After
+pub fn parse_retry_after(s: &str) -> Result<RetryAfterValue> {
+    if let Ok(delay_sec) = s.parse::<u64>() {
+        return Ok(RetryAfterValue::Delay(Duration::new(delay_sec, 0)));
+    }
+    parse_http_date(s)
+        .map(RetryAfterValue::DateTime)
+        .ok_or_else(|| {
+            http_types::Error::from_str(
+                StatusCode::InternalServerError,
+                format!("could not parse Retry-After value: {}", s),
+            )
+        })
+}
+
+/// Parse an RFC 7231 HTTP-date in one of the three formats still seen in the wild: IMF-fixdate
+/// (the preferred form), obsolete RFC 850, and obsolete asctime.
+fn parse_http_date(s: &str) -> Option<SystemTime> {
+    const FORMATS: &[&str] = &[
+        "%a, %d %b %Y %T GMT", // IMF-fixdate, e.g. "Sun, 06 Nov 1994 08:49:37 GMT"
+        "%A, %d-%b-%y %T GMT", // RFC 850, e.g. "Sunday, 06-Nov-94 08:49:37 GMT"
+        "%a %b %e %T %Y",      // asctime, e.g. "Sun Nov  6 08:49:37 1994"
+    ];
+    FORMATS.iter().find_map(|format| {
+        let naive = chrono::NaiveDateTime::parse_from_str(s, format).ok()?;
+        // `NaiveDateTime::timestamp` is deprecated as of chrono 0.4.35 in favor of going
+        // through an explicit `Utc` offset first (these formats are always GMT/UTC anyway).
+        Some(system_time_from_unix_secs(naive.and_utc().timestamp()))
+    })
+}
+
+/// Convert a (possibly negative, i.e. pre-1970) Unix timestamp into a `SystemTime`.
+fn system_time_from_unix_secs(secs: i64) -> SystemTime {
+    if secs >= 0 {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs as u64)
+    } else {
+        SystemTime::UNIX_EPOCH - Duration::from_secs((-secs) as u64)
+    }
+}
+
+/// The jitter algorithm used to space out retries that aren't driven by a server-supplied
+/// delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackoffScheme {
+    /// Decorrelated jitter: each delay is drawn from `[base, prev * 3]` and capped at
+    /// `max_delay_sec`, where `prev` is the delay picked on the previous attempt (starting at
+    /// `base`). This tends to grow over time while avoiding the thundering-herd effect of a
+    /// fixed exponential backoff.
+    Decorrelated,
+    /// Full jitter: each delay is drawn uniformly from `[0, min(max_delay_sec, base * 2^attempt)]`.
+    Full,
+}
+
+/// A backoff policy applied when retrying requests that aren't accompanied by a usable
+/// server-supplied delay, so transient failures still get retried with growing delays instead
+/// of being given up on immediately.
+#[derive(Debug, Clone)]
+pub struct BackoffPolicy {
+    base: Duration,
+    scheme: BackoffScheme,
+}
+
+impl BackoffPolicy {
+    /// Create a new backoff policy with the given base delay and jitter scheme.
+    pub fn new(base: Duration, scheme: BackoffScheme) -> Self {
+        Self { base, scheme }
+    }
+}
+
+/// A small, dependency-free xorshift PRNG used to jitter backoff delays. This middleware only
+/// needs low-quality randomness, so pulling in a full RNG crate isn't worth it.
 #[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15);
+        Rng(seed | 1)
+    }
+
+    /// xorshift64 step, returning a value in `[0.0, 1.0)`.
+    fn next_f64(&mut self) -> f64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A uniformly distributed duration in `[low, high)`.
+    fn uniform(&mut self, low: Duration, high: Duration) -> Duration {
+        if high <= low {
+            return low;
+        }
+        let span = (high - low).as_secs_f64();
+        low + Duration::from_secs_f64(self.next_f64() * span)
+    }
+}
+
+/// A predicate deciding whether an error returned while sending a request is transient (a
+/// connection reset, a timeout, a DNS hiccup) and therefore worth retrying, as opposed to a
+/// permanent failure that retrying won't fix.
+pub type IsTransientError = Arc<dyn Fn(&Error) -> bool + Send + Sync>;
+
+/// The default transient-error predicate: retries connection-level errors (refused, reset,
+/// aborted, timed out) and, when the error carries an HTTP status, 408 Request Timeout or 504
+/// Gateway Timeout.
+fn is_transient_error(err: &Error) -> bool {
+    if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+        return matches!(
+            io_err.kind(),
+            std::io::ErrorKind::ConnectionRefused
+                | std::io::ErrorKind::ConnectionReset
+                | std::io::ErrorKind::ConnectionAborted
+                | std::io::ErrorKind::TimedOut
+        );
+    }
+    matches!(
+        err.status(),
+        StatusCode::RequestTimeout | StatusCode::GatewayTimeout
+    )
+}
+
+/// A middleware which retries throttled requests.
 pub struct RetryAfter {
     attempts: u8,
     max_delay_sec: u16,
     deadline_sec: u16,
+    backoff: Option<BackoffPolicy>,
+    reset_headers: Vec<ResetHeader>,
+    default_delay: Option<Duration>,
+    is_transient_error: IsTransientError,
+    retry_log_level: log::Level,
+}
+
+impl std::fmt::Debug for RetryAfter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryAfter")
+            .field("attempts", &self.attempts)
+            .field("max_delay_sec", &self.max_delay_sec)
+            .field("deadline_sec", &self.deadline_sec)
+            .field("backoff", &self.backoff)
+            .field("reset_headers", &self.reset_headers)
+            .field("default_delay", &self.default_delay)
+            .field("retry_log_level", &self.retry_log_level)
+            .finish()
+    }
 }
 
 impl RetryAfter {
@@ -47,13 +270,14 @@ impl RetryAfter {
     ///
     /// This middleware checks for a retry-after header upon receiving one of the following response codes:
     /// - 301 Moved Permanently
+    /// - 408 Request Timeout
     /// - 429 Too Many Requests
     /// - 503 Service Unavailable
+    /// - 504 Gateway Timeout
     ///
-    /// # Errors
-    ///
-    /// An error will be passed through the middleware stack if the value of the `Retry-after`
-    /// header is not a validly parsing integer or date.
+    /// If the configured reset header is missing or fails to parse, this falls back to the
+    /// configured backoff policy and, failing that, the default delay — it never surfaces an
+    /// error of its own for a malformed header.
     ///
     /// # Examples
     ///
@@ -71,96 +295,233 @@ impl RetryAfter {
             attempts,
             max_delay_sec,
             deadline_sec,
+            backoff: None,
+            reset_headers: default_reset_headers(),
+            default_delay: None,
+            is_transient_error: Arc::new(is_transient_error),
+            retry_log_level: log::Level::Info,
         }
     }
+
+    /// Configure the log level used when logging a retry or a give-up. Defaults to
+    /// `log::Level::Info`; set it to e.g. `log::Level::Debug` to keep routine retries out of
+    /// normal logs, or to `log::Level::Warn` to make them more visible.
+    pub fn with_retry_log_level(mut self, level: log::Level) -> Self {
+        self.retry_log_level = level;
+        self
+    }
+
+    /// Override the predicate used to decide whether an error returned while sending a request
+    /// (a connection reset, a timeout, a DNS hiccup) is worth retrying. The default predicate
+    /// retries connection-level errors and errors carrying a 408/504 status; replace it to retry
+    /// more or fewer kinds of failed send. Responses with a retryable status code are retried
+    /// regardless of this predicate.
+    pub fn with_retryable_error_predicate(
+        mut self,
+        predicate: impl Fn(&Error) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.is_transient_error = Arc::new(predicate);
+        self
+    }
+
+    /// Configure a default delay to retry after when a retryable response carries no valid
+    /// delay header (and no backoff policy resolves one), instead of giving up immediately.
+    /// This mirrors the common "default retry duration" behavior used for rate-limited
+    /// endpoints that omit a `Retry-After` header altogether.
+    pub fn with_default_delay(mut self, default_delay: Duration) -> Self {
+        self.default_delay = Some(default_delay);
+        self
+    }
+
+    /// Configure a backoff policy to use when a retryable response doesn't carry a usable
+    /// delay header, so transient failures still get retried with a growing delay instead of
+    /// being given up on immediately.
+    pub fn with_backoff_policy(mut self, policy: BackoffPolicy) -> Self {
+        self.backoff = Some(policy);
+        self
+    }
+
+    /// Register an additional header to inspect for a retry delay, alongside the default
+    /// `Retry-After` header. This is useful for gateways that expose rate limit resets through
+    /// headers like `X-RateLimit-Reset`. When a response carries more than one usable reset
+    /// header, the **maximum** resulting delay is used.
+    pub fn with_reset_header(mut self, header: ResetHeader) -> Self {
+        self.reset_headers.push(header);
+        self
+    }
+
+    /// Compute the next backoff delay, if a backoff policy is configured, advancing `prev` for
+    /// the next call.
+    fn backoff_delay(&self, rng: &mut Rng, prev: &mut Option<Duration>, count: u8) -> Option<Duration> {
+        self.backoff.as_ref().map(|policy| {
+            let delay = match policy.scheme {
+                BackoffScheme::Decorrelated => {
+                    let prev_delay = prev.unwrap_or(policy.base);
+                    rng.uniform(policy.base, prev_delay.saturating_mul(3))
+                }
+                BackoffScheme::Full => {
+                    let exp = (policy.base.as_secs_f64() * 2f64.powi(i32::from(count)))
+                        .min(self.max_delay_sec as f64);
+                    rng.uniform(Duration::ZERO, Duration::from_secs_f64(exp))
+                }
+            };
+            let delay = Duration::from_secs_f64(delay.as_secs_f64().min(self.max_delay_sec as f64));
+            *prev = Some(delay);
+            delay
+        })
+    }
+}
+
+/// `Retry-After` is registered once, under `ResetFormat::RetryAfter`, since it alone may be
+/// sent as either an integer number of seconds or an HTTP-date; registering it twice (once per
+/// format) would make the response's `Retry-After` value get inspected, and logged, twice.
+fn default_reset_headers() -> Vec<ResetHeader> {
+    vec![ResetHeader::new(headers::RETRY_AFTER, ResetFormat::RetryAfter)]
 }
 
 #[async_trait::async_trait]
 impl Middleware for RetryAfter {
     #[allow(missing_doc_code_examples)]
-    async fn handle(&self, mut req: Request, client: Client, next: Next<'_>) -> Result<Response> {
+    async fn handle(&self, req: Request, client: Client, next: Next<'_>) -> Result<Response> {
         let mut count: u8 = 0;
         let mut accumulated_duration = Duration::ZERO;
+        let mut rng = Rng::new();
+        let mut prev_backoff = self.backoff.as_ref().map(|policy| policy.base);
 
-        let mut base_url = req.url().clone();
-
-        while count < self.attempts {
+        loop {
             let r: Request = req.clone();
-            let res: Response = client.send(r).await?;
-            if RETRY_AFTER_CODES.contains(&res.status()) {
-                if let Some(retry) = res.header(headers::RETRY_AFTER) {
-                    // header present, parse it to extract delay
-                    let retry_header_value = retry.last().as_str();
+            // Run the rest of the middleware chain for every attempt, so that a retried
+            // request is actually the one whose response gets returned below, rather than a
+            // throwaway probe followed by a fresh request.
+            let res: Response = match next.clone().run(r, client.clone()).await {
+                Ok(res) => res,
+                Err(err) => {
+                    if count >= self.attempts || !(self.is_transient_error)(&err) {
+                        return Err(err);
+                    }
                     print(
-                        log::Level::Info,
+                        self.retry_log_level,
                         format_args!(
-                            "{} {} response contained retry header {} {}",
+                            "{} {} failed with a transient error, retrying: {}",
                             req.method(),
                             req.url(),
-                            headers::RETRY_AFTER,
-                            retry_header_value,
+                            err,
                         ),
                     );
-                    let delay = if let Ok(delay_sec) = retry_header_value.parse::<u64>() {
-                        Some(Duration::new(delay_sec, 0))
-                    } else if let Ok(delay_sec) = delay_from_date_str(retry_header_value) {
-                        Some(delay_sec)
-                    } else {
-                        // invalid retry-after header
-                        None
-                    };
-                    match delay {
-                        // delay valid, apply it unless it exceeds limits
+                    let delay = self
+                        .backoff_delay(&mut rng, &mut prev_backoff, count)
+                        .or(self.default_delay);
+
+                    match apply_delay(
+                        delay,
+                        self.max_delay_sec,
+                        self.deadline_sec,
+                        &mut accumulated_duration,
+                    ) {
                         Some(delay) => {
-                            if (self.max_delay_sec as f32) < delay.as_secs_f32() {
-                                break; // stop retry behavior
-                            }
-                            accumulated_duration += delay;
-                            if (self.deadline_sec as f32) < accumulated_duration.as_secs_f32() {
-                                break; // stop retry behavior
-                            }
                             count += 1;
                             task::sleep(delay).await; // sleep an retry
+                            continue;
                         }
-                        // delay invalid, continue processing
                         None => {
-                            break; // stop retry behavior
-                                   // log.warn!(
-                                   //     "Invalid retry-after header ('{}') in response {}",
-                                   //     &retry_header_value,
-                                   //     &res.status()
-                                   // );
+                            print(
+                                self.retry_log_level,
+                                format_args!(
+                                    "{} {} giving up after {} attempt(s)",
+                                    req.method(),
+                                    req.url(),
+                                    count,
+                                ),
+                            );
+                            return Err(err);
                         }
                     }
                 }
-            } else {
-                // headers::RETRY_AFTER not present, no retry
-                break;
+            };
+
+            if count >= self.attempts || !RETRY_AFTER_CODES.contains(&res.status()) {
+                return Ok(res);
             }
-        }
 
-        Ok(next.run(req, client).await?)
+            // Inspect every configured reset header present on the response, and retry
+            // after the maximum of the resulting delays, matching gateways that expose
+            // more than one of these headers at once.
+            let header_delay = self
+                .reset_headers
+                .iter()
+                .filter_map(|header| {
+                    let retry = res.header(&header.name)?;
+                    let retry_header_value = retry.last().as_str();
+                    print(
+                        self.retry_log_level,
+                        format_args!(
+                            "{} {} response contained retry header {} {}",
+                            req.method(),
+                            req.url(),
+                            header.name,
+                            retry_header_value,
+                        ),
+                    );
+                    resolve_reset_header(header, retry_header_value)
+                })
+                .max();
+
+            // No usable header value: fall back to the configured backoff policy, if any,
+            // instead of giving up immediately.
+            let delay = header_delay.or_else(|| self.backoff_delay(&mut rng, &mut prev_backoff, count));
+
+            // Still nothing usable: fall back to the flat default delay, if configured, so
+            // non-compliant endpoints that omit the header entirely still get retried.
+            let delay = delay.or(self.default_delay);
+
+            match apply_delay(
+                delay,
+                self.max_delay_sec,
+                self.deadline_sec,
+                &mut accumulated_duration,
+            ) {
+                // delay valid, apply it unless it exceeds limits
+                Some(delay) => {
+                    count += 1;
+                    task::sleep(delay).await; // sleep an retry
+                }
+                // delay invalid (or missing and no fallback configured): stop retrying and
+                // return the response we already have.
+                None => {
+                    print(
+                        self.retry_log_level,
+                        format_args!(
+                            "{} {} giving up after {} attempt(s)",
+                            req.method(),
+                            req.url(),
+                            count,
+                        ),
+                    );
+                    return Ok(res);
+                }
+            }
+        }
     }
 }
 
-/// If cannot parse, returns error.
-/// If parsed value is in future, returns duration to wait.
-/// If parsed value is not in future, returns zero duration.
-/// reference: https://docs.rs/hyper/0.11.7/src/hyper/header/shared/httpdate.rs.html#35-44
-fn delay_from_date_str(s: &str) -> Result<Duration> {
-    match time::strptime(s, "%a, %d %b %Y %T %Z")
-        .or_else(|_| time::strptime(s, "%A, %d-%b-%y %T %Z"))
-        .or_else(|_| time::strptime(s, "%c"))
-    {
-        Ok(t) => Ok(NaiveDateTime::from_timestamp(t.to_timespec().sec, 0)
-            .signed_duration_since(chrono::offset::Utc::now().naive_utc())
-            .to_std()
-            .unwrap_or(Duration::ZERO)),
-        Err(e) => Err(http_types::Error::from_str(
-            StatusCode::InternalServerError,
-            format!("could not parse date: {}", s),
-        )),
+/// Apply the retry limits (`max_delay_sec`, `deadline_sec`) to a candidate delay, returning
+/// `None` if the delay is missing or would exceed either limit.
+fn apply_delay(
+    delay: Option<Duration>,
+    max_delay_sec: u16,
+    deadline_sec: u16,
+    accumulated_duration: &mut Duration,
+) -> Option<Duration> {
+    let delay = delay?;
+    if (max_delay_sec as f32) < delay.as_secs_f32() {
+        return None;
+    }
+    let total = *accumulated_duration + delay;
+    if (deadline_sec as f32) < total.as_secs_f32() {
+        return None;
     }
+    *accumulated_duration = total;
+    Some(delay)
 }
 
 impl Default for RetryAfter {
@@ -170,6 +531,11 @@ impl Default for RetryAfter {
             attempts: 3,
             max_delay_sec: 30,
             deadline_sec: 60,
+            backoff: None,
+            reset_headers: default_reset_headers(),
+            default_delay: None,
+            is_transient_error: Arc::new(is_transient_error),
+            retry_log_level: log::Level::Info,
         }
     }
 }
@@ -183,3 +549,68 @@ fn print(level: log::Level, msg: Arguments<'_>) {
             .build(),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_delay_floors_past_datetime_to_zero() {
+        let past = SystemTime::now() - Duration::from_secs(60);
+        assert_eq!(RetryAfterValue::DateTime(past).into_delay(), Duration::ZERO);
+    }
+
+    #[test]
+    fn into_delay_passes_through_a_plain_delay() {
+        let value = RetryAfterValue::Delay(Duration::from_secs(5));
+        assert_eq!(value.into_delay(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn parse_retry_after_accepts_seconds() {
+        assert_eq!(
+            parse_retry_after("120").unwrap(),
+            RetryAfterValue::Delay(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn parse_retry_after_accepts_an_http_date() {
+        let value = parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(
+            value,
+            RetryAfterValue::DateTime(SystemTime::UNIX_EPOCH + Duration::from_secs(784111777))
+        );
+    }
+
+    #[test]
+    fn full_jitter_backoff_stays_within_max_delay_for_large_counts() {
+        let retry = RetryAfter {
+            max_delay_sec: 10,
+            backoff: Some(BackoffPolicy::new(Duration::from_secs(1), BackoffScheme::Full)),
+            ..RetryAfter::default()
+        };
+        let mut rng = Rng::new();
+        let mut prev = None;
+        // A large attempt count would overflow `Duration` if the exponent weren't clamped
+        // before being drawn from, rather than only clamped after.
+        let delay = retry.backoff_delay(&mut rng, &mut prev, 200).unwrap();
+        assert!(delay <= Duration::from_secs(10));
+    }
+
+    #[test]
+    fn decorrelated_backoff_stays_within_max_delay() {
+        let retry = RetryAfter {
+            max_delay_sec: 5,
+            backoff: Some(BackoffPolicy::new(
+                Duration::from_secs(1),
+                BackoffScheme::Decorrelated,
+            )),
+            ..RetryAfter::default()
+        };
+        let mut rng = Rng::new();
+        let mut prev = Some(Duration::from_secs(100));
+        let delay = retry.backoff_delay(&mut rng, &mut prev, 1).unwrap();
+        assert!(delay <= Duration::from_secs(5));
+    }
+}